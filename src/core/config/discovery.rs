@@ -0,0 +1,190 @@
+//! Upward directory search for configuration files, mirroring how Deno and Cargo
+//! locate their project-level config: starting at the current directory, walk up
+//! through parents until a match is found, the user's home directory is reached,
+//! or the filesystem root is hit, then fall back to the user's XDG config home.
+//!
+//! `discover_upward` and `existing_standard_locations` both search over
+//! `candidate_directories`, the single definition of "everywhere Maestro looks
+//! for a config file". Keeping them on one list means the loader can never find
+//! a file through one path that the ambiguity check (the latter function) didn't
+//! also see, or vice versa.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// The outcome of an upward search for `file_name`: the first matching path, if
+/// any, canonicalized, plus every directory that was checked (useful for
+/// reporting to the user when nothing is found).
+pub(crate) struct Discovery {
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) searched: Vec<PathBuf>,
+}
+
+/// Searches for `file_name` across `candidate_directories`, in order, stopping
+/// at the first match.
+pub(crate) fn discover_upward(file_name: &str) -> Discovery {
+    discover_upward_in(file_name, &candidate_directories(current_dir(), home_dir(), xdg_config_home()))
+}
+
+/// Same as `discover_upward`, but searches the given list of directories
+/// instead of computing it from the real environment, so tests can point it
+/// at a scratch directory tree instead of relying on the process's real
+/// `$HOME`/`XDG_CONFIG_HOME`/filesystem ancestry.
+fn discover_upward_in(file_name: &str, dirs: &[PathBuf]) -> Discovery {
+    let mut searched = Vec::new();
+
+    for dir in dirs {
+        let candidate = dir.join(file_name);
+        if candidate.is_file() {
+            let resolved = fs::canonicalize(&candidate).unwrap_or(candidate);
+            return Discovery { path: Some(resolved), searched };
+        }
+        searched.push(dir.clone());
+    }
+
+    Discovery { path: None, searched }
+}
+
+fn current_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+/// Every directory Maestro searches for a config file, in precedence order:
+/// `start`, each parent above it up to (and including) `home` or the
+/// filesystem root, and finally `xdg_config_home`
+/// (`$XDG_CONFIG_HOME/maestro`, or `~/.config/maestro` if unset).
+///
+/// Parameterized on `start`/`home`/`xdg_config_home` rather than reading the
+/// real environment directly, so tests can search a scratch directory tree
+/// instead of the process's real `$HOME` and filesystem ancestry.
+fn candidate_directories(start: PathBuf, home: Option<PathBuf>, xdg_config_home: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut dir = start;
+
+    loop {
+        dirs.push(dir.clone());
+        if home.as_deref() == Some(dir.as_path()) || !dir.pop() {
+            break;
+        }
+    }
+
+    if let Some(config_home) = xdg_config_home {
+        dirs.push(config_home.join("maestro"));
+    }
+
+    dirs
+}
+
+/// Every candidate directory that actually contains `file_name`, deduplicated by
+/// canonical path (e.g. when the current directory and an ancestor coincide).
+pub(crate) fn existing_standard_locations(file_name: &str) -> Vec<PathBuf> {
+    existing_standard_locations_in(file_name, candidate_directories(current_dir(), home_dir(), xdg_config_home()))
+}
+
+/// Same as `existing_standard_locations`, but checks the given list of
+/// directories instead of computing it from the real environment.
+fn existing_standard_locations_in(file_name: &str, dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+    dirs.into_iter()
+        .map(|dir| dir.join(file_name))
+        .filter(|path| path.is_file())
+        .filter_map(|path| fs::canonicalize(&path).ok())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn xdg_config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| home_dir().map(|home| home.join(".config")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    /// Builds a scratch directory tree under a fresh temp directory, with a
+    /// `start` directory nested a few levels below a synthetic `home`, so
+    /// discovery only ever walks directories these tests created themselves
+    /// rather than the real `$HOME`/filesystem ancestry. Each test uses its own
+    /// uniquely-named root, so unlike the rest of this crate's filesystem tests,
+    /// these don't need `#[serial]`.
+    fn scratch_tree(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("maestro_discovery_test_{}", name));
+        let start = root.join("home").join("project").join("nested");
+        fs::create_dir_all(&start).expect("failed to create scratch directory tree");
+        (root, start)
+    }
+
+    #[test]
+    fn test_discover_upward_finds_file_in_current_directory() {
+        let (root, start) = scratch_tree("finds_file");
+        let file_name = "discovery_test_marker.json";
+        File::create(start.join(file_name)).expect("failed to create marker file");
+
+        let discovery =
+            discover_upward_in(file_name, &candidate_directories(start, Some(root.join("home")), None));
+
+        fs::remove_dir_all(&root).expect("failed to clean up scratch directory tree");
+
+        let found = discovery.path.expect("expected marker file to be found");
+        assert!(found.is_absolute());
+        assert_eq!(found.file_name().map(|name| name.to_str().unwrap()), Some(file_name));
+    }
+
+    #[test]
+    fn test_discover_upward_reports_searched_directories_when_missing() {
+        let (root, start) = scratch_tree("reports_searched");
+
+        let discovery = discover_upward_in(
+            "file_that_should_never_exist.json",
+            &candidate_directories(start, Some(root.join("home")), None),
+        );
+
+        fs::remove_dir_all(&root).expect("failed to clean up scratch directory tree");
+
+        assert!(discovery.path.is_none());
+        assert!(!discovery.searched.is_empty());
+    }
+
+    #[test]
+    fn test_existing_standard_locations_reports_single_candidate() {
+        let (root, start) = scratch_tree("single_candidate");
+        let file_name = "ambiguity_single_marker.json";
+        File::create(start.join(file_name)).expect("failed to create marker file");
+
+        let candidates = existing_standard_locations_in(
+            file_name,
+            candidate_directories(start, Some(root.join("home")), None),
+        );
+
+        fs::remove_dir_all(&root).expect("failed to clean up scratch directory tree");
+
+        assert_eq!(candidates.len(), 1);
+    }
+
+    #[test]
+    fn test_existing_standard_locations_reports_multiple_candidates() {
+        let (root, start) = scratch_tree("multiple_candidates");
+        let xdg_config_home = root.join("xdg_config_home");
+        let xdg_maestro_dir = xdg_config_home.join("maestro");
+        fs::create_dir_all(&xdg_maestro_dir).expect("failed to create fake XDG config dir");
+
+        let file_name = "ambiguity_multi_marker.json";
+        File::create(start.join(file_name)).expect("failed to create cwd marker file");
+        File::create(xdg_maestro_dir.join(file_name)).expect("failed to create XDG marker file");
+
+        let candidates = existing_standard_locations_in(
+            file_name,
+            candidate_directories(start, Some(root.join("home")), Some(xdg_config_home)),
+        );
+
+        fs::remove_dir_all(&root).expect("failed to clean up scratch directory tree");
+
+        assert_eq!(candidates.len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Deep-merges layered `serde_json::Value` configuration trees before the merged
+//! tree is deserialized into a `Maestro`.
+
+use serde_json::Value;
+
+/// Deep-merges `overlay` into `base`. Scalars and arrays in `overlay` replace the
+/// corresponding value in `base`, objects are merged key by key, and the special
+/// `workspaces` array is merged by `name` instead of being replaced wholesale: a
+/// workspace present in both is merged field-by-field, and a workspace only present
+/// in `overlay` is appended.
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    if !base.is_object() || !overlay.is_object() {
+        *base = overlay;
+        return;
+    }
+
+    let Value::Object(base_map) = base else { unreachable!() };
+    let Value::Object(overlay_map) = overlay else { unreachable!() };
+
+    for (key, overlay_value) in overlay_map {
+        if key == "workspaces" {
+            let slot = base_map.entry(key).or_insert_with(|| Value::Array(Vec::new()));
+            merge_workspaces(slot, overlay_value);
+        } else if let Some(base_value) = base_map.get_mut(&key) {
+            deep_merge(base_value, overlay_value);
+        } else {
+            base_map.insert(key, overlay_value);
+        }
+    }
+}
+
+fn merge_workspaces(base: &mut Value, overlay: Value) {
+    if !base.is_array() || !overlay.is_array() {
+        *base = overlay;
+        return;
+    }
+    let Value::Array(base_workspaces) = base else { unreachable!() };
+    let Value::Array(overlay_workspaces) = overlay else { unreachable!() };
+
+    for overlay_workspace in overlay_workspaces {
+        let name = overlay_workspace.get("name").and_then(Value::as_str).map(str::to_string);
+        let existing = name.as_ref().and_then(|name| {
+            base_workspaces
+                .iter_mut()
+                .find(|workspace| workspace.get("name").and_then(Value::as_str) == Some(name.as_str()))
+        });
+        match existing {
+            Some(existing_workspace) => deep_merge(existing_workspace, overlay_workspace),
+            None => base_workspaces.push(overlay_workspace),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deep_merge_overrides_scalars() {
+        let mut base = json!({ "a": 1, "b": { "c": 2 } });
+        let overlay = json!({ "b": { "c": 3 } });
+        deep_merge(&mut base, overlay);
+        assert_eq!(base, json!({ "a": 1, "b": { "c": 3 } }));
+    }
+
+    #[test]
+    fn test_deep_merge_appends_new_workspace() {
+        let mut base = json!({ "workspaces": [ { "name": "A", "workspace_path": "/a" } ] });
+        let overlay = json!({ "workspaces": [ { "name": "B", "workspace_path": "/b" } ] });
+        deep_merge(&mut base, overlay);
+        assert_eq!(
+            base,
+            json!({ "workspaces": [
+                { "name": "A", "workspace_path": "/a" },
+                { "name": "B", "workspace_path": "/b" }
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_deep_merge_overrides_workspace_by_name() {
+        let mut base = json!({ "workspaces": [ { "name": "A", "workspace_path": "/a" } ] });
+        let overlay = json!({ "workspaces": [ { "name": "A", "workspace_path": "/overridden" } ] });
+        deep_merge(&mut base, overlay);
+        assert_eq!(
+            base,
+            json!({ "workspaces": [ { "name": "A", "workspace_path": "/overridden" } ] })
+        );
+    }
+}
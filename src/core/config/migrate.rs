@@ -0,0 +1,86 @@
+//! Versioned migration of the persisted configuration format. Every config layer
+//! carries a `version` field; `migrate` walks a value forward through the ordered
+//! step functions below until it matches `CURRENT_VERSION`, so old user files keep
+//! loading even after the schema changes (a field rename, say).
+//!
+//! To add a new version, append a `v{n}_to_v{n+1}` step to `STEPS` and bump
+//! `CURRENT_VERSION`.
+
+use serde_json::Value;
+
+use crate::core::model::error::MaestroError;
+use crate::core::model::maestro::CURRENT_VERSION;
+
+/// A single migration step: takes a value at some version and returns it
+/// migrated to the next version.
+type MigrationStep = fn(Value) -> Result<Value, MaestroError>;
+
+/// Migration steps, in order: `STEPS[i]` migrates a value from version `i + 1` to
+/// version `i + 2`. Empty until the schema changes for the first time.
+const STEPS: &[MigrationStep] = &[];
+
+/// Reads the `version` field off a raw config layer, defaulting to `1` when absent
+/// (the version field itself was introduced in version 1).
+pub(crate) fn layer_version(value: &Value) -> u32 {
+    value.get("version").and_then(Value::as_u64).map(|version| version as u32).unwrap_or(1)
+}
+
+/// Migrates `value` from version `from` up to `CURRENT_VERSION`, stamping the
+/// result with the current version once it is reached.
+pub(crate) fn migrate(value: Value, from: u32) -> Result<Value, MaestroError> {
+    if from == 0 {
+        return Err(MaestroError::ConfigError(
+            "Configuration version 0 is invalid; versions start at 1".to_string(),
+        ));
+    }
+    if from > CURRENT_VERSION {
+        return Err(MaestroError::ConfigError(format!(
+            "Configuration version {} is newer than the latest supported version {}; upgrade Maestro to load it",
+            from, CURRENT_VERSION
+        )));
+    }
+
+    let mut migrated = value;
+    let mut version = from;
+    while version < CURRENT_VERSION {
+        let step = STEPS.get((version - 1) as usize).ok_or_else(|| {
+            MaestroError::ConfigError(format!("No migration step defined from configuration version {}", version))
+        })?;
+        migrated = step(migrated)?;
+        version += 1;
+    }
+
+    if let Value::Object(map) = &mut migrated {
+        map.insert("version".to_string(), Value::from(CURRENT_VERSION));
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_layer_version_defaults_to_one_when_absent() {
+        assert_eq!(layer_version(&json!({ "workspaces": [] })), 1);
+    }
+
+    #[test]
+    fn test_migrate_stamps_current_version() {
+        let migrated = migrate(json!({ "workspaces": [] }), 1).expect("migration should succeed");
+        assert_eq!(migrated.get("version").and_then(Value::as_u64), Some(CURRENT_VERSION as u64));
+    }
+
+    #[test]
+    fn test_migrate_rejects_future_version() {
+        let result = migrate(json!({ "workspaces": [] }), CURRENT_VERSION + 1);
+        assert!(matches!(result, Err(MaestroError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_migrate_rejects_version_zero() {
+        let result = migrate(json!({ "workspaces": [], "version": 0 }), 0);
+        assert!(matches!(result, Err(MaestroError::ConfigError(_))));
+    }
+}
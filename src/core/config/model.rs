@@ -1,13 +1,23 @@
 use serde::{Serialize, Deserialize};
+use crate::core::model::maestro::CURRENT_VERSION;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
-    config_file_path: String,
+    #[serde(default = "default_version")]
+    pub(crate) version: u32,
+    pub(crate) config_file_path: String,
+}
+
+/// The version to assume when a persisted `Config` omits the `version` field
+/// entirely, i.e. the version it had before the field was introduced.
+fn default_version() -> u32 {
+    1
 }
 
 impl Config {
     pub fn new(config_file_path: String) -> Self {
         Self {
+            version: CURRENT_VERSION,
             config_file_path,
         }
     }
@@ -16,6 +26,7 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CURRENT_VERSION,
             config_file_path: "default.json".to_string(),
         }
     }
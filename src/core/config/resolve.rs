@@ -0,0 +1,304 @@
+//! Resolves the final `Maestro` configuration by merging the `Default`, `User`,
+//! `Repo`, `Env` and `CommandArg` layers in increasing precedence, modeled on how
+//! `jj` resolves its settings.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::core::config::discovery::discover_upward;
+use crate::core::config::merge::deep_merge;
+use crate::core::config::migrate::{layer_version, migrate};
+use crate::core::config::source::{AnnotatedValue, ConfigSource};
+use crate::core::config::store::{read_pointed_user_config, REPO_CONFIG_FILE};
+use crate::core::model::error::MaestroError;
+use crate::core::model::maestro::Maestro;
+
+const WORKSPACE_PATH_ENV_VAR: &str = "MAESTRO_WORKSPACE_PATH";
+const CONFIG_PATH_ENV_VAR: &str = "MAESTRO_CONFIG";
+
+/// The result of resolving a `Maestro` configuration across all of its layers:
+/// the merged configuration itself, its field-by-field provenance, and the
+/// resolved path of the `User` layer's pointer file (if one was found), so
+/// callers can report which config was actually used.
+pub struct ResolvedConfig {
+    pub maestro: Maestro,
+    pub provenance: Vec<AnnotatedValue>,
+    pub resolved_user_config_path: Option<PathBuf>,
+}
+
+/// Resolves the effective `Maestro` configuration, merging every layer that is
+/// present. `explicit_config_path` is the `--config` override, which takes
+/// precedence over every other layer; if not given, the `MAESTRO_CONFIG`
+/// environment variable is used instead. Either one also disambiguates the
+/// `User` layer's pointer file lookup, since the caller has already said
+/// exactly which config to use.
+pub fn resolve_config(explicit_config_path: Option<PathBuf>) -> Result<ResolvedConfig, MaestroError> {
+    let explicit_config_path =
+        explicit_config_path.or_else(|| std::env::var_os(CONFIG_PATH_ENV_VAR).map(PathBuf::from));
+
+    let mut layers: Vec<(ConfigSource, Value)> = Vec::new();
+
+    let user_lookup = read_pointed_user_config(explicit_config_path.is_some())?;
+    if let Some(user_value) = user_lookup.value {
+        layers.push((ConfigSource::User, migrate_layer(user_value)?));
+    }
+    if let Some(repo_path) = discover_upward(REPO_CONFIG_FILE).path {
+        if let Some(repo_value) = read_layer(&repo_path)? {
+            layers.push((ConfigSource::Repo, migrate_layer(repo_value)?));
+        }
+    }
+    if let Some(explicit_path) = explicit_config_path {
+        let arg_value = read_layer(&explicit_path)?.ok_or_else(|| {
+            MaestroError::ConfigError(format!(
+                "Explicit config file '{}' does not exist",
+                explicit_path.display()
+            ))
+        })?;
+        layers.push((ConfigSource::CommandArg, migrate_layer(arg_value)?));
+    }
+
+    if layers.is_empty() {
+        let searched = user_lookup
+            .searched
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(MaestroError::ConfigError(format!(
+            "Failed to load Maestro configuration: no configuration found\nSearched: {}\nEnsure Maestro is configured",
+            searched
+        )));
+    }
+
+    layers.sort_by_key(|(source, _)| source.precedence());
+
+    let mut provenance: BTreeMap<Vec<String>, AnnotatedValue> = BTreeMap::new();
+    for (source, value) in &layers {
+        annotate(value, *source, &mut Vec::new(), &mut provenance);
+    }
+
+    // `apply_env_overrides` stands in for the `Env` layer without being a real
+    // entry in `layers`, so it has to be slotted into the merge by hand at
+    // `Env`'s precedence: after every layer below it (`Default`/`User`/`Repo`)
+    // and before every layer at or above it (`CommandArg`), or an explicit
+    // `--config` would be silently clobbered by the env var.
+    let env_precedence = ConfigSource::Env.precedence();
+    let mut merged = Value::Object(serde_json::Map::new());
+    let mut layers_above_env = Vec::new();
+    for (source, value) in layers {
+        if source.precedence() < env_precedence {
+            deep_merge(&mut merged, value);
+        } else {
+            layers_above_env.push(value);
+        }
+    }
+    apply_env_overrides(&mut merged, &mut provenance);
+    for value in layers_above_env {
+        // A higher layer that sets a workspace's path overrides the env var, so
+        // the `Env` provenance entry for that path no longer applies.
+        if let Some(Value::Array(workspaces)) = value.get("workspaces") {
+            for workspace in workspaces {
+                if let (Some(name), Some(_)) =
+                    (workspace.get("name").and_then(Value::as_str), workspace.get("workspace_path"))
+                {
+                    provenance.remove(&vec!["workspaces".to_string(), name.to_string(), "workspace_path".to_string()]);
+                }
+            }
+        }
+        deep_merge(&mut merged, value);
+    }
+
+    let mut maestro: Maestro = serde_json::from_value(merged).map_err(|err| {
+        MaestroError::SerdeError(format!("Failed to parse merged Maestro configuration: {}", err))
+    })?;
+    maestro.validate()?;
+
+    Ok(ResolvedConfig {
+        maestro,
+        provenance: provenance.into_values().collect(),
+        resolved_user_config_path: user_lookup.pointer_path,
+    })
+}
+
+/// Migrates a freshly-read layer up to the current configuration version before
+/// it takes part in merging, so every layer is on a common schema regardless of
+/// which Maestro version last wrote it.
+fn migrate_layer(value: Value) -> Result<Value, MaestroError> {
+    let version = layer_version(&value);
+    migrate(value, version)
+}
+
+/// Reads a configuration file directly as a JSON `Value`, returning `None` if it
+/// does not exist.
+pub(crate) fn read_layer(path: &Path) -> Result<Option<Value>, MaestroError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path).map_err(|err| {
+        MaestroError::ConfigError(format!("Failed to read configuration file '{}': {}", path.display(), err))
+    })?;
+    let value: Value = serde_json::from_str(&contents).map_err(|err| {
+        MaestroError::SerdeError(format!("Failed to parse configuration file '{}': {}", path.display(), err))
+    })?;
+    Ok(Some(value))
+}
+
+/// Applies environment variable overrides directly onto the merged value. Unlike
+/// the file-based layers, these target a specific known field rather than being
+/// merged wholesale, since env vars describe a single override rather than a full
+/// configuration tree.
+///
+/// `MAESTRO_WORKSPACE_PATH` only applies when the merged config has exactly one
+/// workspace: `workspaces` is keyed by name, so a single unqualified env var
+/// can't sensibly target one entry out of several without silently clobbering
+/// the rest.
+fn apply_env_overrides(merged: &mut Value, provenance: &mut BTreeMap<Vec<String>, AnnotatedValue>) {
+    let Ok(workspace_path) = std::env::var(WORKSPACE_PATH_ENV_VAR) else {
+        return;
+    };
+    let Value::Object(map) = merged else { return };
+    let Some(Value::Array(workspaces)) = map.get_mut("workspaces") else {
+        return;
+    };
+    if workspaces.len() != 1 {
+        return;
+    }
+    let Some(Value::Object(workspace_map)) = workspaces.get_mut(0) else { return };
+    let Some(name) = workspace_map.get("name").and_then(Value::as_str).map(str::to_string) else {
+        return;
+    };
+    workspace_map.insert("workspace_path".to_string(), Value::String(workspace_path.clone()));
+    let path = vec!["workspaces".to_string(), name, "workspace_path".to_string()];
+    provenance.insert(
+        path.clone(),
+        AnnotatedValue { path, source: ConfigSource::Env, value: Value::String(workspace_path) },
+    );
+}
+
+fn annotate(
+    value: &Value,
+    source: ConfigSource,
+    prefix: &mut Vec<String>,
+    out: &mut BTreeMap<Vec<String>, AnnotatedValue>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                prefix.push(key.clone());
+                annotate(value, source, prefix, out);
+                prefix.pop();
+            }
+        }
+        _ => {
+            out.insert(
+                prefix.clone(),
+                AnnotatedValue { path: prefix.clone(), source, value: value.clone() },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::fs::File;
+
+    #[test]
+    #[serial]
+    fn test_apply_env_overrides_targets_single_workspace() {
+        std::env::set_var(WORKSPACE_PATH_ENV_VAR, "/overridden");
+        let mut merged = serde_json::json!({ "workspaces": [ { "name": "A", "workspace_path": "/path/a" } ] });
+        let mut provenance = BTreeMap::new();
+
+        apply_env_overrides(&mut merged, &mut provenance);
+
+        std::env::remove_var(WORKSPACE_PATH_ENV_VAR);
+        assert_eq!(merged["workspaces"][0]["workspace_path"], "/overridden");
+    }
+
+    #[test]
+    #[serial]
+    fn test_apply_env_overrides_skips_multi_workspace_configs() {
+        std::env::set_var(WORKSPACE_PATH_ENV_VAR, "/overridden");
+        let mut merged = serde_json::json!({ "workspaces": [
+            { "name": "A", "workspace_path": "/path/a" },
+            { "name": "B", "workspace_path": "/path/b" }
+        ] });
+        let mut provenance = BTreeMap::new();
+
+        apply_env_overrides(&mut merged, &mut provenance);
+
+        std::env::remove_var(WORKSPACE_PATH_ENV_VAR);
+        assert_eq!(merged["workspaces"][0]["workspace_path"], "/path/a");
+        assert_eq!(merged["workspaces"][1]["workspace_path"], "/path/b");
+    }
+
+    #[test]
+    #[serial]
+    fn test_repo_layer_is_discovered_from_a_subdirectory() {
+        use std::io::Write;
+
+        let original_dir = std::env::current_dir().expect("failed to read cwd");
+        let mut repo_file = File::create(REPO_CONFIG_FILE).expect("Failed to create repo config file");
+        repo_file
+            .write_all(br#"{ "workspaces": [ { "name": "Repo", "description": "d", "workspace_path": "/repo" } ] }"#)
+            .expect("Failed to write repo config file");
+
+        let nested_dir = original_dir.join("resolve_nested_repo_test_dir");
+        fs::create_dir_all(&nested_dir).expect("Failed to create nested directory");
+        std::env::set_current_dir(&nested_dir).expect("Failed to chdir into nested directory");
+
+        let repo_value = discover_upward(REPO_CONFIG_FILE).path.and_then(|path| read_layer(&path).ok().flatten());
+
+        std::env::set_current_dir(&original_dir).expect("Failed to restore cwd");
+        fs::remove_dir_all(&nested_dir).expect("Failed to remove nested directory");
+        fs::remove_file(REPO_CONFIG_FILE).expect("Failed to remove repo config file");
+
+        assert!(repo_value.is_some(), "expected repo config to be discovered from a subdirectory");
+    }
+
+    #[test]
+    #[serial]
+    fn test_explicit_config_wins_over_workspace_path_env_var() {
+        use std::io::Write;
+
+        let explicit_path = PathBuf::from("resolve_explicit_config_env_precedence_test.json");
+        let mut file = File::create(&explicit_path).expect("Failed to create explicit config file");
+        file.write_all(
+            br#"{ "workspaces": [ { "name": "A", "description": "d", "workspace_path": "/from-command-arg" } ] }"#,
+        )
+        .expect("Failed to write explicit config file");
+
+        std::env::set_var(WORKSPACE_PATH_ENV_VAR, "/from-env");
+
+        let result = resolve_config(Some(explicit_path.clone()));
+
+        std::env::remove_var(WORKSPACE_PATH_ENV_VAR);
+        fs::remove_file(&explicit_path).expect("Failed to remove explicit config file");
+
+        let resolved = result.expect("resolve_config should succeed");
+        assert_eq!(resolved.maestro.workspaces[0].workspace_path, "/from-command-arg");
+    }
+
+    #[test]
+    fn test_annotate_records_leaf_paths_and_source() {
+        let value = serde_json::json!({ "workspaces": [] });
+        let mut out = BTreeMap::new();
+        annotate(&value, ConfigSource::Repo, &mut Vec::new(), &mut out);
+        let annotated = out.get(&vec!["workspaces".to_string()]).expect("workspaces path recorded");
+        assert_eq!(annotated.source, ConfigSource::Repo);
+    }
+
+    #[test]
+    fn test_migrate_layer_stamps_unversioned_layer_to_current() {
+        let migrated = migrate_layer(serde_json::json!({ "workspaces": [] })).expect("migration should succeed");
+        assert_eq!(
+            migrated.get("version").and_then(Value::as_u64),
+            Some(crate::core::model::maestro::CURRENT_VERSION as u64)
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Identifies where a resolved configuration value came from, so callers can explain
+//! (and debug) how the final `Maestro` was assembled out of its layered sources.
+
+use std::fmt;
+
+/// A layer that can contribute values to the final configuration, in increasing
+/// precedence: a `Repo` value overrides a `User` value, an `Env` value overrides
+/// both, and an explicit `CommandArg` value always wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    User,
+    Repo,
+    Env,
+    CommandArg,
+}
+
+impl ConfigSource {
+    /// Relative precedence used when merging layers; a higher value wins on
+    /// conflicting scalar keys.
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            ConfigSource::Default => 0,
+            ConfigSource::User => 1,
+            ConfigSource::Repo => 2,
+            ConfigSource::Env => 3,
+            ConfigSource::CommandArg => 4,
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConfigSource::Default => "default",
+            ConfigSource::User => "user",
+            ConfigSource::Repo => "repo",
+            ConfigSource::Env => "env",
+            ConfigSource::CommandArg => "command-arg",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single effective field in the resolved configuration, annotated with the
+/// layer it ultimately came from. Intended to back a future `maestro config list`
+/// command.
+#[derive(Debug, Clone)]
+pub struct AnnotatedValue {
+    pub path: Vec<String>,
+    pub source: ConfigSource,
+    pub value: serde_json::Value,
+}
@@ -2,28 +2,49 @@
 //! The maestro configuration file is a JSON file that contains the path to the user's configuration file, which can be updated at any time by the user.
 //!
 //! This module includes the following functions:
-//! - `load_config` - Loads the user configuration from a file.
+//! - `load_config` - Loads the effective user configuration, merged across all configuration layers.
 //! - `save_user_config_file` - Saves the user configuration to a file.
 
 use std::fs;
 use std::fs::File;
-use std::path::{PathBuf};
-use std::io::{BufReader};
-use crate::core::model::config::Config;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::core::config::discovery::{discover_upward, existing_standard_locations};
+use crate::core::config::migrate::{layer_version, migrate};
+use crate::core::config::model::Config;
+use crate::core::config::resolve::{resolve_config, read_layer};
 use crate::core::model::error::MaestroError;
 use crate::core::model::maestro::Maestro;
 
-const MAESTRO_CONFIG_FILE: &str = "maestro.json";
+/// Points at the user's configuration file (the `User` layer), discovered by
+/// searching upward from the current directory.
+pub(crate) const MAESTRO_CONFIG_FILE: &str = "maestro.json";
+/// A repo-local configuration file, read directly rather than through a pointer
+/// (the `Repo` layer).
+pub(crate) const REPO_CONFIG_FILE: &str = ".maestro.json";
 
-/// Loads the user configuration from a file.
+/// The result of looking up the `User` layer: the parsed user configuration (if
+/// the pointer file was found and resolved), the pointer file's resolved path
+/// (for callers that want to report which config was actually used), and every
+/// directory that was searched.
+pub(crate) struct UserConfigLookup {
+    pub(crate) value: Option<Value>,
+    pub(crate) pointer_path: Option<PathBuf>,
+    pub(crate) searched: Vec<PathBuf>,
+}
+
+/// Loads the effective Maestro configuration, merging the `Default`, `User`,
+/// `Repo`, `Env` and `CommandArg` layers in increasing precedence.
 ///
 /// # Returns
 ///
-/// A `Result` containing a `Maestro` struct if the file is successfully loaded.
+/// A `Result` containing a `Maestro` struct if at least one layer is found and the
+/// merged result is valid.
 ///
 /// # Errors
 ///
-/// Returns a `MaestroError` if the file cannot be opened or if deserialization fails.
+/// Returns a `MaestroError` if no layer can be found, or if a layer cannot be
+/// parsed, merged, or fails validation.
 ///
 /// # Examples
 ///
@@ -31,48 +52,69 @@ const MAESTRO_CONFIG_FILE: &str = "maestro.json";
 /// let result = load_config();
 /// assert!(result.is_ok());
 /// let maestro: Maestro = result.unwrap();
-/// assert_eq!(maestro.projects.len(), 2);
+/// assert_eq!(maestro.workspaces.len(), 2);
 /// ```
 pub fn load_config() -> Result<Maestro, MaestroError> {
-    let path_buf = PathBuf::from(MAESTRO_CONFIG_FILE);
-    let maestro_config_file = File::open(&path_buf).map_err(|err| {
-        MaestroError::ConfigError(format!(
-            "Failed to load Maestro configuration: {}\nEnsure Maestro is configured",
-            err
-        ))
-    })?;
+    resolve_config(None).map(|resolved| resolved.maestro)
+}
 
-    let reader = BufReader::new(maestro_config_file);
-    let config: Config = serde_json::from_reader(reader).map_err(|err| {
-        MaestroError::SerdeError(format!(
-            "Failed to parse Maestro configuration: {}",
-            err
-        ))
-    })?;
+/// Reads the `User` layer: the `maestro.json` pointer file, discovered by
+/// searching upward from the current directory, followed by the user
+/// configuration file it points to. `value` is `None` (rather than an error) when
+/// the pointer file cannot be found anywhere in the search, so other layers can
+/// still apply.
+///
+/// Before searching, checks every directory `discover_upward` would itself
+/// check for a pointer file; if more than one exists, the pointer is ambiguous
+/// and this returns `MaestroError::AmbiguousConfig` rather than silently picking
+/// one, unless `skip_ambiguity_check` is set (the caller has an explicit
+/// `--config`/`MAESTRO_CONFIG` override and doesn't need the pointer file at
+/// all).
+pub(crate) fn read_pointed_user_config(skip_ambiguity_check: bool) -> Result<UserConfigLookup, MaestroError> {
+    if !skip_ambiguity_check {
+        let candidates = existing_standard_locations(MAESTRO_CONFIG_FILE);
+        if candidates.len() > 1 {
+            return Err(MaestroError::AmbiguousConfig(candidates));
+        }
+    }
 
-    let user_config_file = File::open(&config.config_file_path).map_err(|err| {
+    let discovery = discover_upward(MAESTRO_CONFIG_FILE);
+    let Some(pointer_path) = discovery.path else {
+        return Ok(UserConfigLookup { value: None, pointer_path: None, searched: discovery.searched });
+    };
+
+    let pointer_value = read_layer(&pointer_path)?.ok_or_else(|| {
         MaestroError::ConfigError(format!(
-            "Failed to load user configuration: {}\nEnsure Maestro is configured",
-            err
+            "Maestro configuration '{}' disappeared while loading\nEnsure Maestro is configured",
+            pointer_path.display()
         ))
     })?;
-    let reader = BufReader::new(user_config_file);
+    // The pointer file carries its own `version`, just like the other config
+    // layers, so it needs to go through the same migration pipeline before
+    // deserializing rather than relying solely on serde's default.
+    let pointer_version = layer_version(&pointer_value);
+    let pointer_value = migrate(pointer_value, pointer_version)?;
+    let config: Config = serde_json::from_value(pointer_value).map_err(|err| {
+        MaestroError::SerdeError(format!("Failed to parse Maestro configuration: {}", err))
+    })?;
 
-    let maestro: Maestro = serde_json::from_reader(reader).map_err(|err| {
-        MaestroError::SerdeError(format!(
-            "Failed to parse user configuration: {}",
-            err
+    let user_value = read_layer(Path::new(&config.config_file_path))?.ok_or_else(|| {
+        MaestroError::ConfigError(format!(
+            "Failed to load user configuration: {} does not exist\nEnsure Maestro is configured",
+            config.config_file_path
         ))
     })?;
 
-    // Validate the maestro configuration
-    maestro.validate()?;
-
-    Ok(maestro)
+    Ok(UserConfigLookup { value: Some(user_value), pointer_path: Some(pointer_path), searched: discovery.searched })
 }
 
 /// Saves the user configuration to a file.
 ///
+/// Writes are atomic: the config is serialized to a temporary file in the same
+/// directory and `fs::rename`d into place, so a crash mid-write can't truncate an
+/// existing config. On Unix the file is created with mode `0600`, since it
+/// records local filesystem layout.
+///
 /// # Arguments
 ///
 /// * `user_config_path` - A string that holds the path to the user configuration file.
@@ -83,7 +125,8 @@ pub fn load_config() -> Result<Maestro, MaestroError> {
 ///
 /// # Errors
 ///
-/// Returns a `MaestroError` if the file cannot be created or if serialization fails.
+/// Returns a `MaestroError` if the file cannot be created, permissions cannot be
+/// set, serialization fails, or the rename into place fails.
 ///
 /// # Examples
 ///
@@ -92,14 +135,6 @@ pub fn load_config() -> Result<Maestro, MaestroError> {
 /// assert!(result.is_ok());
 /// ```
 pub fn save_user_config_file(user_config_path: String) -> Result<String, MaestroError> {
-    let path_buf = PathBuf::from(MAESTRO_CONFIG_FILE);
-    let file = File::create(&path_buf).map_err(|err| {
-        MaestroError::ConfigError(format!(
-                "Failed to configure Maestro: {}\nEnsure Maestro has write permissions and reconfigure",
-                err
-        ))
-    })?;
-
     // Convert the path to an absolute path
     let absolute_path = fs::canonicalize(PathBuf::from(user_config_path.to_string()))
         .map_err(|err|
@@ -108,14 +143,63 @@ pub fn save_user_config_file(user_config_path: String) -> Result<String, Maestro
 
     let config = Config::new(absolute_path.to_str().unwrap().to_string());
 
-    serde_json::to_writer_pretty(&file, &config).map_err(|err| {
-        MaestroError::SerdeError(format!(
-            "Failed to configure Maestro: {}",
+    let target_path = PathBuf::from(MAESTRO_CONFIG_FILE);
+    let temp_path = target_path.with_extension("json.tmp");
+
+    write_config_atomically(&temp_path, &target_path, &config)?;
+
+    Ok(absolute_path.to_str().unwrap().to_string())
+}
+
+/// Serializes `config` to `temp_path`, created with restricted permissions from
+/// the outset, then renames it into `target_path`. Best-effort cleans up
+/// `temp_path` if any step fails.
+fn write_config_atomically(temp_path: &Path, target_path: &Path, config: &Config) -> Result<(), MaestroError> {
+    let result = (|| {
+        let file = create_restricted(temp_path)?;
+
+        serde_json::to_writer_pretty(&file, config).map_err(|err| {
+            MaestroError::SerdeError(format!("Failed to configure Maestro: {}", err))
+        })?;
+        file.sync_all().map_err(|err| {
+            MaestroError::IoError(format!("Failed to flush Maestro configuration: {}", err))
+        })?;
+
+        fs::rename(temp_path, target_path).map_err(|err| {
+            MaestroError::IoError(format!("Failed to move Maestro configuration into place: {}", err))
+        })
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(temp_path);
+    }
+    result
+}
+
+/// Creates `path` with mode `0600` on Unix, set atomically at creation time
+/// (rather than `chmod`'d afterward) so the file is never briefly readable
+/// under the default umask.
+#[cfg(unix)]
+fn create_restricted(path: &Path) -> Result<File, MaestroError> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path).map_err(|err| {
+        MaestroError::IoError(format!(
+            "Failed to configure Maestro: {}\nEnsure Maestro has write permissions and reconfigure",
             err
         ))
-    })?;
+    })
+}
 
-    Ok(absolute_path.to_str().unwrap().to_string())
+#[cfg(not(unix))]
+fn create_restricted(path: &Path) -> Result<File, MaestroError> {
+    File::create(path).map_err(|err| {
+        MaestroError::IoError(format!(
+            "Failed to configure Maestro: {}\nEnsure Maestro has write permissions and reconfigure",
+            err
+        ))
+    })
 }
 
 #[cfg(test)]
@@ -170,7 +254,19 @@ mod tests {
     #[test]
     #[serial]
     fn test_load_config_with_no_saved_file() {
+        // Bound the upward search to the current directory: without this, a
+        // stray `maestro.json` anywhere between here and the real `$HOME`
+        // would make this test flaky.
+        let real_home = std::env::var_os("HOME");
+        std::env::set_var("HOME", std::env::current_dir().unwrap());
+
         let load_result = load_config();
+
+        match real_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+
         assert!(load_result.is_err());
     }
 
@@ -247,4 +343,165 @@ mod tests {
         fs::remove_file(MAESTRO_CONFIG_FILE ).expect("Failed to delete test maestro config file");
         fs::remove_file("invalid_user_config.json").expect("Failed to delete test user config file");
     }
+
+    #[test]
+    #[serial]
+    fn test_save_writes_atomically_and_restricts_permissions() {
+        let user_config_path = "atomic_user_config.json".to_string();
+        let mut file = File::create(user_config_path.clone()).expect("Failed to create test config file");
+        file.write_all(br#"{ "workspaces": [] }"#).expect("Failed to write to test config file");
+
+        let save_result = save_user_config_file(user_config_path.clone());
+        assert!(save_result.is_ok());
+
+        // The temporary file used for the atomic rename should not be left behind.
+        let temp_path = PathBuf::from(MAESTRO_CONFIG_FILE).with_extension("json.tmp");
+        assert!(!temp_path.exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(MAESTRO_CONFIG_FILE)
+                .expect("Failed to read maestro config file metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Clean up the files
+        fs::remove_file(MAESTRO_CONFIG_FILE).expect("Failed to delete test maestro config file");
+        fs::remove_file(user_config_path).expect("Failed to delete test user config file");
+    }
+
+    #[test]
+    #[serial]
+    fn test_round_trips_nested_projects() {
+        let user_config_path = "nested_projects_user_config.json".to_string();
+        let mut file = File::create(user_config_path.clone()).expect("Failed to create test config file");
+        let test_config_content = r#"
+        {
+            "workspaces": [
+                {
+                    "name": "WorkspaceA",
+                    "description": "Description for workspaceA",
+                    "workspace_path": "/path/to/workspaceA",
+                    "projects": [
+                        { "name": "ProjectA", "description": "First project" },
+                        { "name": "ProjectB", "description": "Second project" }
+                    ]
+                }
+            ]
+        }
+        "#;
+        file.write_all(test_config_content.as_bytes()).expect("Failed to write to test config file");
+
+        let save_result = save_user_config_file(user_config_path.clone());
+        assert!(save_result.is_ok());
+
+        let load_result = load_config();
+        assert!(load_result.is_ok());
+        let maestro = load_result.unwrap();
+        assert_eq!(maestro.workspaces[0].projects.len(), 2);
+        assert_eq!(maestro.workspaces[0].projects[0].name, "ProjectA");
+        assert_eq!(maestro.workspaces[0].projects[1].name, "ProjectB");
+
+        // Clean up the files
+        fs::remove_file(MAESTRO_CONFIG_FILE).expect("Failed to delete test maestro config file");
+        fs::remove_file(user_config_path).expect("Failed to delete test user config file");
+    }
+
+    #[test]
+    #[serial]
+    fn test_rejects_duplicate_project_names_on_load() {
+        let user_config_path = "duplicate_projects_user_config.json".to_string();
+        let mut file = File::create(user_config_path.clone()).expect("Failed to create test config file");
+        let test_config_content = r#"
+        {
+            "workspaces": [
+                {
+                    "name": "WorkspaceA",
+                    "description": "Description for workspaceA",
+                    "workspace_path": "/path/to/workspaceA",
+                    "projects": [
+                        { "name": "ProjectA", "description": "First project" },
+                        { "name": "ProjectA", "description": "Duplicate project" }
+                    ]
+                }
+            ]
+        }
+        "#;
+        file.write_all(test_config_content.as_bytes()).expect("Failed to write to test config file");
+
+        let save_result = save_user_config_file(user_config_path.clone());
+        assert!(save_result.is_ok());
+
+        let load_result = load_config();
+        match load_result {
+            Err(MaestroError::MaestroConfigValidationError(message)) => {
+                assert!(message.contains("Duplicate project name"));
+            }
+            other => panic!("Expected MaestroError::MaestroConfigValidationError, got {:?}", other),
+        }
+
+        // Clean up the files
+        fs::remove_file(MAESTRO_CONFIG_FILE).expect("Failed to delete test maestro config file");
+        fs::remove_file(user_config_path).expect("Failed to delete test user config file");
+    }
+
+    #[test]
+    #[serial]
+    fn test_load_config_fails_on_ambiguous_pointer_files() {
+        // Bound the upward search to the current directory: without this, a
+        // stray `maestro.json` anywhere between here and the real `$HOME`
+        // would add a third candidate and make this test flaky.
+        let real_home = std::env::var_os("HOME");
+        std::env::set_var("HOME", std::env::current_dir().unwrap());
+
+        // One candidate in the current directory (the normal case)...
+        File::create(MAESTRO_CONFIG_FILE).expect("Failed to create cwd maestro config file");
+
+        // ...and a second one in a faked XDG config home, making the pointer ambiguous.
+        let xdg_config_home = std::env::current_dir().unwrap().join("xdg_config_home_store_test");
+        let xdg_maestro_dir = xdg_config_home.join("maestro");
+        fs::create_dir_all(&xdg_maestro_dir).expect("Failed to create fake XDG config dir");
+        File::create(xdg_maestro_dir.join(MAESTRO_CONFIG_FILE)).expect("Failed to create XDG maestro config file");
+        std::env::set_var("XDG_CONFIG_HOME", &xdg_config_home);
+
+        let load_result = load_config();
+
+        std::env::remove_var("XDG_CONFIG_HOME");
+        match real_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+        fs::remove_file(MAESTRO_CONFIG_FILE).expect("Failed to delete test maestro config file");
+        fs::remove_dir_all(&xdg_config_home).expect("Failed to clean up fake XDG config dir");
+
+        match load_result {
+            Err(MaestroError::AmbiguousConfig(candidates)) => assert_eq!(candidates.len(), 2),
+            other => panic!("Expected MaestroError::AmbiguousConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_load_config_with_unversioned_pointer_file() {
+        let user_config_path = "unversioned_pointer_user_config.json".to_string();
+        let mut file = File::create(user_config_path.clone()).expect("Failed to create test config file");
+        file.write_all(br#"{ "workspaces": [] }"#).expect("Failed to write to test config file");
+
+        // Write the pointer file directly, omitting `version` entirely, to simulate
+        // one written before the version field existed.
+        let mut pointer_file = File::create(MAESTRO_CONFIG_FILE).expect("Failed to create maestro config file");
+        pointer_file
+            .write_all(format!(r#"{{ "config_file_path": "{}" }}"#, user_config_path).as_bytes())
+            .expect("Failed to write maestro config file");
+
+        let load_result = load_config();
+
+        fs::remove_file(MAESTRO_CONFIG_FILE).expect("Failed to delete test maestro config file");
+        fs::remove_file(user_config_path).expect("Failed to delete test user config file");
+
+        assert!(load_result.is_ok());
+    }
 }
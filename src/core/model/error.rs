@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,4 +8,14 @@ pub enum MaestroError {
     SerdeError(String),
     #[error("Config error: {0}")]
     ConfigError(String),
+    #[error("Maestro config validation error: {0}")]
+    MaestroConfigValidationError(String),
+    #[error("IO error: {0}")]
+    IoError(String),
+    #[error(
+        "Ambiguous Maestro configuration: found {} candidates, expected exactly one:\n{}\nConsolidate them into a single file, or disambiguate with --config/MAESTRO_CONFIG",
+        .0.len(),
+        .0.iter().map(|path| format!("  - {}", path.display())).collect::<Vec<_>>().join("\n")
+    )]
+    AmbiguousConfig(Vec<PathBuf>),
 }
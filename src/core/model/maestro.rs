@@ -2,14 +2,27 @@ use serde::{Serialize, Deserialize};
 use crate::core::model::error::MaestroError;
 use crate::core::model::workspace::Workspace;
 
+/// The current version of the persisted Maestro configuration schema. Bump this
+/// alongside a new migration step in `crate::core::config::migrate` whenever the
+/// schema changes.
+pub(crate) const CURRENT_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Maestro {
+    #[serde(default = "default_version")]
+    pub version: u32,
     pub workspaces: Vec<Workspace>,
 }
 
+/// The version to assume when a persisted `Maestro` omits the `version` field
+/// entirely, i.e. the version it had before the field was introduced.
+fn default_version() -> u32 {
+    1
+}
+
 impl Maestro {
-    pub(crate) fn validate(&self) -> Result<(), MaestroError> {
-        for workspace in &self.workspaces {
+    pub(crate) fn validate(&mut self) -> Result<(), MaestroError> {
+        for workspace in &mut self.workspaces {
             workspace.validate()?;
         }
         Ok(())
@@ -22,13 +35,16 @@ mod tests {
 
     #[test]
     fn test_valid_maestro() {
-        let maestro = Maestro {
+        let mut maestro = Maestro {
+            version: CURRENT_VERSION,
             workspaces: vec![
                 Workspace {
                     name: "WorkspaceA".to_string(),
                     description: "Description for workspaceA".to_string(),
                     workspace_path: "/path/to/workspaceA".to_string(),
-                    container_working_dir: None,
+                    last_updated: None,
+                    projects: vec![],
+                    expanded_workspace_path: None,
                 }
             ]
         };
@@ -37,22 +53,27 @@ mod tests {
 
     #[test]
     fn test_invalid_workspace_in_maestro() {
-        let maestro = Maestro {
+        let mut maestro = Maestro {
+            version: CURRENT_VERSION,
             workspaces: vec![
                 Workspace {
                     name: "WorkspaceA".to_string(),
                     description: "Description for workspaceA".to_string(),
                     workspace_path: "/path/to/workspaceA".to_string(),
-                    container_working_dir: None,
+                    last_updated: None,
+                    projects: vec![],
+                    expanded_workspace_path: None,
                 },
                 Workspace {
                     name: "Workspace B".to_string(),
                     description: "Description for workspaceB".to_string(),
                     workspace_path: "/path/to/workspaceB".to_string(),
-                    container_working_dir: None,
+                    last_updated: None,
+                    projects: vec![],
+                    expanded_workspace_path: None,
                 }
             ]
         };
         assert!(maestro.validate().is_err());
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,53 @@
 use serde::{Serialize, Deserialize};
+use regex::Regex;
+use crate::core::model::error::MaestroError;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Project {
     pub(crate) name: String,
     pub(crate) description: String,
 }
+
+impl Project {
+
+    /// Validates the project by checking that the name is a single word (underscores allowed).
+    pub(crate) fn validate(&self) -> Result<(), MaestroError> {
+        let re = Regex::new(r"^\w+$").unwrap();
+        if !re.is_match(&self.name) {
+            return Err(
+                MaestroError::MaestroConfigValidationError("Project name must be a single word (underscores are allowed)".to_string())
+            );
+        }
+        if self.name.is_empty() {
+            return Err(
+                MaestroError::MaestroConfigValidationError("Project name cannot be empty".to_string())
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_project() {
+        let project = Project {
+            name: "ProjectA".to_string(),
+            description: "Description for projectA".to_string(),
+        };
+        assert!(project.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_name_project() {
+        let project = Project {
+            name: "Project A".to_string(),
+            description: "Description for projectA".to_string(),
+        };
+        assert!(project.validate().is_err());
+        assert!(project.validate().unwrap_err().to_string().contains("Project name must be a single word"));
+    }
+}
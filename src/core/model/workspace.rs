@@ -1,20 +1,33 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use regex::Regex;
 use crate::core::model::error::MaestroError;
+use crate::core::model::project::Project;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Workspace {
     pub(crate) name: String,
     pub(crate) description: String,
     pub(crate) workspace_path: String,
-    pub(crate) last_updated: Option<DateTime<Utc>>
+    pub(crate) last_updated: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) projects: Vec<Project>,
+    /// The expanded, absolute form of `workspace_path`, resolved at load time.
+    /// `workspace_path` itself is kept verbatim (e.g. `~/code/$PROJECT`) so it can
+    /// be round-tripped on save without baking in a machine-specific path.
+    #[serde(skip)]
+    pub(crate) expanded_workspace_path: Option<PathBuf>,
 }
 
 impl Workspace {
 
     /// Validates the workspace by checking if the name, and workspace path are not empty. The name must also have no whitespace.
-    pub(crate) fn validate(&self) -> Result<(), MaestroError> {
+    /// Also validates each nested project (rejecting duplicate project names) and expands `~`, `$VAR`/`${VAR}` and
+    /// `%VAR%` references in `workspace_path`, populating `expanded_workspace_path`.
+    pub(crate) fn validate(&mut self) -> Result<(), MaestroError> {
         let re = Regex::new(r"^\w+$").unwrap();
         if !re.is_match(&self.name) {
             return Err(
@@ -32,32 +45,118 @@ impl Workspace {
             );
         }
 
+        let mut seen_project_names = HashSet::new();
+        for project in &self.projects {
+            project.validate()?;
+            if !seen_project_names.insert(project.name.as_str()) {
+                return Err(MaestroError::MaestroConfigValidationError(format!(
+                    "Duplicate project name '{}' in workspace '{}'",
+                    project.name, self.name
+                )));
+            }
+        }
+
+        self.expanded_workspace_path = Some(expand_path_template(&self.workspace_path)?);
+
         Ok(())
     }
 }
 
+/// Expands `~`, `$VAR`/`${VAR}` and `%VAR%` references in `template` and resolves
+/// the result to an absolute path, joining it onto the current directory if it
+/// isn't already absolute.
+fn expand_path_template(template: &str) -> Result<PathBuf, MaestroError> {
+    let mut expanded = template.to_string();
+
+    if expanded == "~" || expanded.starts_with("~/") || expanded.starts_with("~\\") {
+        let home = home_dir().ok_or_else(|| {
+            MaestroError::MaestroConfigValidationError(
+                "Cannot expand '~' in workspace path: home directory is not set".to_string(),
+            )
+        })?;
+        expanded = expanded.replacen('~', &home.to_string_lossy(), 1);
+    }
+
+    let expanded = expand_env_refs(&expanded)?;
+    let path = PathBuf::from(expanded);
+
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        let cwd = std::env::current_dir().map_err(|err| {
+            MaestroError::MaestroConfigValidationError(format!(
+                "Cannot resolve relative workspace path '{}': {}",
+                template, err
+            ))
+        })?;
+        Ok(cwd.join(path))
+    }
+}
+
+/// Substitutes `$VAR`, `${VAR}` and `%VAR%` references with the corresponding
+/// environment variable's value, erroring if any referenced variable is unset.
+fn expand_env_refs(input: &str) -> Result<String, MaestroError> {
+    let re = Regex::new(r"\$\{(\w+)\}|\$(\w+)|%(\w+)%").unwrap();
+    let mut undefined_var: Option<String> = None;
+
+    let expanded = re.replace_all(input, |captures: &regex::Captures| {
+        let name = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .or_else(|| captures.get(3))
+            .expect("regex match always has one of the three groups")
+            .as_str();
+        match std::env::var(name) {
+            Ok(value) => value,
+            Err(_) => {
+                undefined_var = Some(name.to_string());
+                String::new()
+            }
+        }
+    });
+    let expanded = expanded.into_owned();
+
+    match undefined_var {
+        Some(name) => Err(MaestroError::MaestroConfigValidationError(format!(
+            "Workspace path references undefined environment variable '{}'",
+            name
+        ))),
+        None => Ok(expanded),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
 
     #[test]
     fn test_valid_workspace() {
-        let workspace = Workspace {
+        let mut workspace = Workspace {
             name: "WorkspaceA".to_string(),
             description: "Description for workspaceA".to_string(),
             workspace_path: "/path/to/workspaceA".to_string(),
-            last_updated: None
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
         };
         assert!(workspace.validate().is_ok());
+        assert_eq!(workspace.expanded_workspace_path, Some(PathBuf::from("/path/to/workspaceA")));
     }
 
     #[test]
     fn test_invalid_name_workspace() {
-        let workspace = Workspace {
+        let mut workspace = Workspace {
             name: "Workspace A".to_string(),
             description: "Description for workspaceA".to_string(),
             workspace_path: "workspace".to_string(),
-            last_updated: None
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
         };
         assert!(workspace.validate().is_err());
         assert!(workspace.validate().unwrap_err().to_string().contains("Name must be a single word"));
@@ -65,13 +164,106 @@ mod tests {
 
     #[test]
     fn test_empty_workspace_path_workspace() {
-        let workspace = Workspace {
+        let mut workspace = Workspace {
             name: "WorkspaceA".to_string(),
             description: "Description for workspaceA".to_string(),
             workspace_path: "".to_string(),
-            last_updated: None
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
         };
         assert!(workspace.validate().is_err());
         assert!(workspace.validate().unwrap_err().to_string().contains("Workspace path cannot be empty"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[serial]
+    fn test_tilde_expands_to_home_directory() {
+        std::env::set_var("HOME", "/home/maestro-test");
+        let mut workspace = Workspace {
+            name: "WorkspaceA".to_string(),
+            description: "Description for workspaceA".to_string(),
+            workspace_path: "~/code/workspaceA".to_string(),
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
+        };
+        assert!(workspace.validate().is_ok());
+        assert_eq!(
+            workspace.expanded_workspace_path,
+            Some(PathBuf::from("/home/maestro-test/code/workspaceA"))
+        );
+        std::env::remove_var("HOME");
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_var_expands_dollar_and_brace_syntax() {
+        std::env::set_var("MAESTRO_TEST_ROOT", "/srv/maestro");
+        let mut workspace = Workspace {
+            name: "WorkspaceA".to_string(),
+            description: "Description for workspaceA".to_string(),
+            workspace_path: "${MAESTRO_TEST_ROOT}/a".to_string(),
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
+        };
+        assert!(workspace.validate().is_ok());
+        assert_eq!(workspace.expanded_workspace_path, Some(PathBuf::from("/srv/maestro/a")));
+        workspace.workspace_path = "$MAESTRO_TEST_ROOT/b".to_string();
+        assert!(workspace.validate().is_ok());
+        assert_eq!(workspace.expanded_workspace_path, Some(PathBuf::from("/srv/maestro/b")));
+        std::env::remove_var("MAESTRO_TEST_ROOT");
+    }
+
+    #[test]
+    #[serial]
+    fn test_undefined_env_var_fails_loudly() {
+        std::env::remove_var("MAESTRO_TEST_UNDEFINED");
+        let mut workspace = Workspace {
+            name: "WorkspaceA".to_string(),
+            description: "Description for workspaceA".to_string(),
+            workspace_path: "$MAESTRO_TEST_UNDEFINED/a".to_string(),
+            last_updated: None,
+            projects: vec![],
+            expanded_workspace_path: None,
+        };
+        let result = workspace.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("undefined environment variable"));
+    }
+
+    #[test]
+    fn test_duplicate_project_name_rejected() {
+        let mut workspace = Workspace {
+            name: "WorkspaceA".to_string(),
+            description: "Description for workspaceA".to_string(),
+            workspace_path: "/path/to/workspaceA".to_string(),
+            last_updated: None,
+            projects: vec![
+                Project { name: "ProjectA".to_string(), description: "First".to_string() },
+                Project { name: "ProjectA".to_string(), description: "Second".to_string() },
+            ],
+            expanded_workspace_path: None,
+        };
+        let result = workspace.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Duplicate project name"));
+    }
+
+    #[test]
+    fn test_unique_project_names_accepted() {
+        let mut workspace = Workspace {
+            name: "WorkspaceA".to_string(),
+            description: "Description for workspaceA".to_string(),
+            workspace_path: "/path/to/workspaceA".to_string(),
+            last_updated: None,
+            projects: vec![
+                Project { name: "ProjectA".to_string(), description: "First".to_string() },
+                Project { name: "ProjectB".to_string(), description: "Second".to_string() },
+            ],
+            expanded_workspace_path: None,
+        };
+        assert!(workspace.validate().is_ok());
+    }
+}